@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+pub type Point = (usize, usize);
+
+fn manhattan(a: Point, b: Point) -> u32 {
+    ((a.0 as i64 - b.0 as i64).abs() + (a.1 as i64 - b.1 as i64).abs()) as u32
+}
+
+fn neighbors(p: Point, width: usize, height: usize) -> Vec<Point> {
+    let (x, y) = p;
+    let mut ret = vec![];
+    if y > 0 {
+        ret.push((x, y - 1));
+    }
+    if y + 1 < height {
+        ret.push((x, y + 1));
+    }
+    if x > 0 {
+        ret.push((x - 1, y));
+    }
+    if x + 1 < width {
+        ret.push((x + 1, y));
+    }
+    ret
+}
+
+// Open-set entry ordered by f = g + h, smallest first (BinaryHeap is a max-heap,
+// so Ord is reversed).
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct OpenEntry {
+    f: u32,
+    point: Point,
+}
+
+impl Ord for OpenEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+    }
+}
+
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A* search over a `width` x `height` grid with a Manhattan heuristic. `blocked`
+// reports whether a cell cannot be entered (occupied by another player, or a
+// wall). The goal cell itself is never checked against `blocked`, so a path
+// may end on a blocked goal. Returns the path from the first step after
+// `start` up to and including `goal`, or None if no such path exists.
+pub fn find_path(
+    start: Point,
+    goal: Point,
+    width: usize,
+    height: usize,
+    blocked: impl Fn(Point) -> bool,
+) -> Option<Vec<Point>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Point, Point> = HashMap::new();
+    let mut g_score: HashMap<Point, u32> = HashMap::new();
+
+    g_score.insert(start, 0);
+    open.push(OpenEntry {
+        f: manhattan(start, goal),
+        point: start,
+    });
+
+    while let Some(OpenEntry { point, .. }) = open.pop() {
+        if point == goal {
+            return Some(reconstruct_path(&came_from, point));
+        }
+
+        let g = g_score[&point];
+        for next in neighbors(point, width, height) {
+            if next != goal && blocked(next) {
+                continue;
+            }
+
+            let tentative_g = g + 1;
+            if tentative_g < *g_score.get(&next).unwrap_or(&u32::MAX) {
+                came_from.insert(next, point);
+                g_score.insert(next, tentative_g);
+                open.push(OpenEntry {
+                    f: tentative_g + manhattan(next, goal),
+                    point: next,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+fn reconstruct_path(came_from: &HashMap<Point, Point>, mut current: Point) -> Vec<Point> {
+    let mut path = vec![current];
+    while let Some(&prev) = came_from.get(&current) {
+        path.push(prev);
+        current = prev;
+    }
+    path.reverse();
+    path.remove(0); // drop the start cell itself
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_around_a_blocking_wall() {
+        // 3x3 grid, middle column blocked except the bottom row:
+        // S # .
+        // . # .
+        // . . G
+        let blocked = |p: Point| p == (1, 0) || p == (1, 1);
+        let path = find_path((0, 0), (2, 2), 3, 3, blocked).unwrap();
+
+        assert_eq!(path.last(), Some(&(2, 2)));
+        assert!(path.iter().all(|&p| !blocked(p)));
+    }
+
+    #[test]
+    fn routes_around_a_blocking_occupant() {
+        // Same shape as the wall case, but `blocked` here stands in for
+        // another player's current cell rather than a wall tile.
+        let occupied = |p: Point| p == (1, 0) || p == (1, 1);
+        let path = find_path((0, 0), (2, 2), 3, 3, occupied).unwrap();
+
+        assert_eq!(path.last(), Some(&(2, 2)));
+        assert!(path.iter().all(|&p| !occupied(p)));
+    }
+
+    #[test]
+    fn returns_none_when_the_goal_is_fully_walled_off() {
+        // Same 3x3 grid, but now the middle column is blocked all the way
+        // down, sealing (2, *) off from (0, *) entirely.
+        let blocked = |p: Point| p == (1, 0) || p == (1, 1) || p == (1, 2);
+        assert_eq!(find_path((0, 0), (2, 2), 3, 3, blocked), None);
+    }
+}
@@ -0,0 +1,53 @@
+// A double-buffered grid: each tick reads a frozen `front` while writing
+// next-step state into `back`, then `switch()` swaps the two. This gives
+// callers a stable snapshot to read during a simulation step without paying
+// for a full clone of it every tick.
+pub struct DoubleBuffer<T> {
+    front: Box<T>,
+    back: Box<T>,
+}
+
+impl<T> DoubleBuffer<T> {
+    pub fn new(front: Box<T>, back: Box<T>) -> Self {
+        DoubleBuffer { front, back }
+    }
+
+    pub fn front(&self) -> &T {
+        &self.front
+    }
+
+    pub fn back_mut(&mut self) -> &mut T {
+        &mut self.back
+    }
+
+    // Both views at once: the frozen front to read, and back to write
+    // next-step state into.
+    pub fn front_back_mut(&mut self) -> (&T, &mut T) {
+        (&self.front, &mut self.back)
+    }
+
+    pub fn switch(&mut self) {
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DoubleBuffer;
+
+    #[test]
+    fn switch_swaps_front_and_back() {
+        let mut buf = DoubleBuffer::new(Box::new(1_u32), Box::new(2_u32));
+        assert_eq!(*buf.front(), 1);
+
+        *buf.back_mut() = 3;
+        buf.switch();
+        assert_eq!(*buf.front(), 3);
+
+        let (front, back) = buf.front_back_mut();
+        assert_eq!(*front, 3);
+        *back = 4;
+        buf.switch();
+        assert_eq!(*buf.front(), 4);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+pub type Point = (usize, usize);
+
+// Transform multipliers (xx, xy, yx, yy) mapping local (col, row) coordinates
+// to offsets from the origin, one entry per octant.
+const OCTANTS: [[i64; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+// Recursive shadowcasting field of view (Bjorn Bergstrom's algorithm,
+// roguebasin.com/index.php/FOV_using_recursive_shadowcasting).
+// Returns `origin` plus every cell strictly closer than `radius` to it
+// that isn't hidden behind a cell for which `blocked` returns true.
+// Visibility is not symmetric: a cell being visible from `origin` does not
+// guarantee that `origin` is visible from that cell.
+pub fn field_of_view(
+    origin: Point,
+    radius: usize,
+    width: usize,
+    height: usize,
+    blocked: impl Fn(Point) -> bool,
+) -> HashSet<Point> {
+    let mut visible = HashSet::new();
+    visible.insert(origin);
+
+    for mult in &OCTANTS {
+        cast_light(
+            origin,
+            1,
+            1.0,
+            0.0,
+            radius as i64,
+            mult,
+            width as i64,
+            height as i64,
+            &blocked,
+            &mut visible,
+        );
+    }
+
+    visible
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light(
+    origin: Point,
+    row: i64,
+    mut start_slope: f64,
+    end_slope: f64,
+    radius: i64,
+    mult: &[i64; 4],
+    width: i64,
+    height: i64,
+    blocked: &impl Fn(Point) -> bool,
+    visible: &mut HashSet<Point>,
+) {
+    if start_slope < end_slope {
+        return;
+    }
+
+    let radius_sq = radius * radius;
+    let mut blocked_prev = false;
+    let mut next_start_slope = start_slope;
+
+    for dist in row..=radius {
+        let dy = -dist;
+        let mut dx = -dist - 1;
+        loop {
+            dx += 1;
+            if dx > 0 {
+                break;
+            }
+
+            let l_slope = (dx as f64 - 0.5) / (dy as f64 + 0.5);
+            let r_slope = (dx as f64 + 0.5) / (dy as f64 - 0.5);
+
+            if start_slope < r_slope {
+                continue;
+            } else if end_slope > l_slope {
+                break;
+            }
+
+            let gx = origin.0 as i64 + dx * mult[0] + dy * mult[1];
+            let gy = origin.1 as i64 + dx * mult[2] + dy * mult[3];
+            let in_bounds = gx >= 0 && gy >= 0 && gx < width && gy < height;
+            let is_blocked = !in_bounds || blocked((gx as usize, gy as usize));
+
+            if in_bounds && dx * dx + dy * dy < radius_sq {
+                visible.insert((gx as usize, gy as usize));
+            }
+
+            if blocked_prev {
+                if is_blocked {
+                    next_start_slope = r_slope;
+                    continue;
+                }
+                blocked_prev = false;
+                start_slope = next_start_slope;
+            } else if is_blocked && dist < radius {
+                blocked_prev = true;
+                next_start_slope = r_slope;
+                cast_light(
+                    origin,
+                    dist + 1,
+                    start_slope,
+                    l_slope,
+                    radius,
+                    mult,
+                    width,
+                    height,
+                    blocked,
+                    visible,
+                );
+            }
+        }
+
+        if blocked_prev {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wall_blocks_the_cell_directly_behind_it() {
+        let origin = (5, 5);
+        let wall = (5, 4);
+        let behind_wall = (5, 3);
+
+        let visible = field_of_view(origin, 5, 10, 10, |p| p == wall);
+
+        assert!(visible.contains(&wall));
+        assert!(!visible.contains(&behind_wall));
+    }
+
+    #[test]
+    fn open_area_within_radius_is_visible() {
+        let origin = (5, 5);
+        let nearby = (6, 5);
+
+        let visible = field_of_view(origin, 5, 10, 10, |_| false);
+
+        assert!(visible.contains(&origin));
+        assert!(visible.contains(&nearby));
+    }
+
+    #[test]
+    fn nothing_beyond_radius_is_visible() {
+        let origin = (5, 5);
+        let far_away = (9, 9);
+
+        let visible = field_of_view(origin, 2, 10, 10, |_| false);
+
+        assert!(!visible.contains(&far_away));
+    }
+}
@@ -1,21 +1,54 @@
 use std::fmt::Display;
 
+mod astar;
+mod doublebuffer;
+mod fov;
+mod recorder;
+mod scenario;
+
+use astar::{find_path, Point};
 use bracket_lib::prelude::*;
+use doublebuffer::DoubleBuffer;
+use fov::field_of_view;
 use rand::distributions::WeightedIndex;
 use rand::prelude::{Distribution, IteratorRandom};
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use recorder::{Recorder, Sample};
+use scenario::load_walls;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter; // etc.
 
+type Grid = [[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT];
+type WallGrid = [[Tile; SCREEN_WIDTH]; SCREEN_HEIGHT];
+type InitResult = (Vec<Player>, Box<Grid>, Statistic, Vec<Point>);
+type InitFn = Box<dyn Fn(&mut StdRng) -> InitResult>;
+
+// Static map geometry. Wall is impassable for movement, pathfinding, and
+// line of sight; everything else behaves as open floor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Floor,
+    Wall,
+}
+
 #[derive(Default)]
 struct Statistic {
     inflected: u32,
     immune: u32,
     susceptible: u32,
+    exposed: u32,
+    recovered: u32,
+    dead: u32,
 }
 impl Statistic {
     fn total(&self) -> u32 {
-        self.inflected + self.immune + self.susceptible
+        self.inflected
+            + self.immune
+            + self.susceptible
+            + self.exposed
+            + self.recovered
+            + self.dead
     }
 }
 
@@ -24,26 +57,49 @@ impl Display for Statistic {
         write!(
             f,
             "Inflected: {}\n
+            Exposed: {}\n
             Immune: {}\n
-            Susceptible: {}\n",
-            self.inflected, self.immune, self.susceptible
+            Recovered: {}\n
+            Susceptible: {}\n
+            Dead: {}\n",
+            self.inflected,
+            self.exposed,
+            self.immune,
+            self.recovered,
+            self.susceptible,
+            self.dead
         )
     }
 }
 
 impl Statistic {}
+fn empty_grid() -> Box<Grid> {
+    Box::new([const { [const { None::<Player> }; SCREEN_WIDTH] }; SCREEN_HEIGHT])
+}
+
+// Resets every cell to None in place, without allocating a new grid or
+// cloning the Players it currently holds.
+fn clear_grid(grid: &mut Grid) {
+    grid.iter_mut()
+        .flatten()
+        .for_each(|cell| *cell = None);
+}
+
 struct State {
     players: Vec<Player>,
-    map: Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]>,
+    map: DoubleBuffer<Grid>,
     frame_time: f32,
-    init_fn: Box<
-        dyn Fn() -> (
-            Vec<Player>,
-            Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]>,
-            Statistic,
-        ),
-    >,
+    init_fn: InitFn,
     statistic: Statistic,
+    rng: StdRng,
+    // Shared "home"/"market" points players path toward once a goal is assigned.
+    anchors: Vec<Point>,
+    // Static wall layout, loaded once at startup and untouched by restart().
+    walls: Box<WallGrid>,
+    // Current simulation step, used as the time axis for `recorder`.
+    tick: u32,
+    // Time series of compartment counts, one Sample per `advance()` call.
+    recorder: Recorder,
 }
 
 impl GameState for State {
@@ -52,8 +108,12 @@ impl GameState for State {
 
         ctx.cls_bg(NAVY);
 
+        self.render_walls(ctx);
+
         self.show_info(ctx);
 
+        self.render_sparkline(ctx);
+
         self.players.iter_mut().for_each(|player| {
             // Add player to screen
             player.render(ctx);
@@ -63,44 +123,15 @@ impl GameState for State {
             self.restart();
         }
 
+        if let Some(VirtualKeyCode::S) = ctx.key {
+            self.recorder
+                .write_csv(EPIDEMIC_CSV_PATH)
+                .expect("failed to write epidemic series csv");
+        }
+
         if self.frame_time > FRAME_TIME {
             self.frame_time = 0.0; // reset
-
-            let mut x_y_before_move = vec![];
-
-            self.players.iter_mut().for_each(|player| {
-                // Update map
-                player.update_position_in_map(&mut self.map);
-            });
-
-            /*
-            FIX STATE: State is a moment which be based on for next health-check
-             */
-            let fixed_map = self.map.clone();
-
-            self.players.iter_mut().for_each(|player| {
-                /* Handle something BUT don't change any state. */
-                // Handle player health
-                if player.meet_infected(&fixed_map) {
-                    if player.health_state == HealthState::Susceptible {
-                        player.health_state = HealthState::Inflected;
-                        self.statistic.inflected += 1;
-                        self.statistic.susceptible -= 1;
-                    }
-                }
-
-                // Deal with movement
-                if let Some(old_position) = player.keep_moving(&mut self.map) {
-                    x_y_before_move.push(old_position);
-                }
-
-                // println!("Step: {}", player.steps);
-            });
-
-            // Update map by deleting block that player have been gone.
-            x_y_before_move.iter().for_each(|p| {
-                self.map[p.1][p.0].take();
-            });
+            self.advance();
         }
     }
 }
@@ -108,34 +139,142 @@ impl GameState for State {
 impl State {
     fn new(
         players: Vec<Player>,
-        map: Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]>,
-        init_fn: Box<
-            dyn Fn() -> (
-                Vec<Player>,
-                Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]>,
-                Statistic,
-            ),
-        >,
+        map: Box<Grid>,
+        init_fn: InitFn,
         statistic: Statistic,
+        rng: StdRng,
+        anchors: Vec<Point>,
+        walls: Box<WallGrid>,
     ) -> Self {
         State {
             players,
-            map,
+            map: DoubleBuffer::new(map, empty_grid()),
             frame_time: 0.0,
             init_fn,
             statistic,
+            rng,
+            anchors,
+            walls,
+            tick: 0,
+            recorder: Recorder::default(),
         }
     }
 
     fn restart(&mut self) {
-        let (players, map, statistic) = (self.init_fn)();
+        let (players, map, statistic, anchors) = (self.init_fn)(&mut self.rng);
         self.players = players;
-        self.map = map;
+        self.map = DoubleBuffer::new(map, empty_grid());
         self.statistic = statistic;
+        self.anchors = anchors;
+        self.tick = 0;
+        self.recorder = Recorder::default();
+    }
+
+    // One simulation step: movement, infection/incubation/recovery rolls, and
+    // bookkeeping. Factored out of GameState::tick so headless batch runs can
+    // drive the model without a BTerm to pace them.
+    fn advance(&mut self) {
+        // `back` becomes next tick's occupancy snapshot: each live player is
+        // written into it at its post-move position below, so it must start
+        // empty rather than carry over whatever it held two ticks ago.
+        clear_grid(self.map.back_mut());
+
+        self.players.iter_mut().for_each(|player| {
+            /* Handle something BUT don't change any state. */
+            // Handle player health
+            player.ticks_in_state += 1;
+            match player.health_state {
+                HealthState::Susceptible => {
+                    // infection_probability does a field_of_view scan of
+                    // every visible player, which dominates run_headless's
+                    // per-tick cost; skip it entirely once there's no one
+                    // left to catch it from.
+                    let p = if self.statistic.inflected == 0 {
+                        0.0
+                    } else {
+                        player.infection_probability(self.map.front(), &self.walls)
+                    };
+                    if p > 0.0 && self.rng.gen_bool(p) {
+                        player.health_state = HealthState::Exposed;
+                        player.ticks_in_state = 0;
+                        self.statistic.susceptible -= 1;
+                        self.statistic.exposed += 1;
+                    }
+                }
+                HealthState::Exposed => {
+                    if self.rng.gen_bool(1.0 / INCUBATION_TICKS) {
+                        player.health_state = HealthState::Inflected;
+                        player.ticks_in_state = 0;
+                        self.statistic.exposed -= 1;
+                        self.statistic.inflected += 1;
+                    }
+                }
+                HealthState::Inflected => {
+                    if self.rng.gen_bool(1.0 / INFECTIOUS_TICKS) {
+                        self.statistic.inflected -= 1;
+                        if self.rng.gen_bool(MORTALITY) {
+                            player.is_dead = true;
+                            self.statistic.dead += 1;
+                        } else {
+                            player.health_state = HealthState::Immune;
+                            player.ticks_in_state = 0;
+                            self.statistic.recovered += 1;
+                        }
+                    }
+                }
+                HealthState::Immune => {}
+            }
+
+            // Pick a new destination once the current one is reached (or there
+            // isn't one yet), producing crowding at shared anchor points. A
+            // player who just gave up on a stuck goal sits out idle_cooldown
+            // ticks first -- otherwise Idle would be treated as "ready for a
+            // new goal" on the very next tick, and a player stuck against one
+            // unreachable anchor would be immediately pointed at another,
+            // potentially just as bad.
+            let reached_goal = match player.goal {
+                AIGoal::GoTo(p) | AIGoal::Return(p) => (player.x, player.y) == p,
+                AIGoal::Idle if player.idle_cooldown > 0 => {
+                    player.idle_cooldown -= 1;
+                    false
+                }
+                AIGoal::Idle => true,
+            };
+            if reached_goal && !self.anchors.is_empty() {
+                let anchor = self.anchors[self.rng.gen_range(0..self.anchors.len())];
+                player.goal = if self.rng.gen_bool(0.5) {
+                    AIGoal::GoTo(anchor)
+                } else {
+                    AIGoal::Return(player.home)
+                };
+            }
+
+            // Deal with movement. Dead players are simply left out of `back`
+            // so they disappear from the grid once buffers switch below.
+            if !player.is_dead {
+                let (front, back) = self.map.front_back_mut();
+                player.keep_moving(front, back, &self.walls, &mut self.rng);
+            }
+
+            // println!("Step: {}", player.steps);
+        });
+
+        self.players.retain(|player| !player.is_dead);
+        self.map.switch();
+
+        self.recorder.record(Sample {
+            tick: self.tick,
+            susceptible: self.statistic.susceptible,
+            exposed: self.statistic.exposed,
+            inflected: self.statistic.inflected,
+            recovered: self.statistic.recovered,
+            dead: self.statistic.dead,
+        });
+        self.tick += 1;
     }
 
     fn show_info(&self, ctx: &mut BTerm) {
-        ctx.print(0, 0, "Press R to restart.");
+        ctx.print(0, 0, "Press R to restart, S to save the epidemic curve as CSV.");
         ctx.print_color(
             0,
             1,
@@ -146,20 +285,41 @@ impl State {
         ctx.print_color(
             0,
             2,
+            ORANGE,
+            BLACK,
+            format!("    Exposed: {}", &self.statistic.exposed),
+        );
+        ctx.print_color(
+            0,
+            3,
             GREEN,
             BLACK,
             format!("     Immune: {}", &self.statistic.immune),
         );
         ctx.print_color(
             0,
-            3,
+            4,
+            GREEN,
+            BLACK,
+            format!("  Recovered: {}", &self.statistic.recovered),
+        );
+        ctx.print_color(
+            0,
+            5,
             YELLOW,
             BLACK,
             format!("Susceptible: {}", &self.statistic.susceptible),
         );
-        ctx.print(0, 4, format!("      Total: {}", &self.statistic.total()));
+        ctx.print_color(
+            0,
+            6,
+            GRAY,
+            BLACK,
+            format!("       Dead: {}", &self.statistic.dead),
+        );
+        ctx.print(0, 7, format!("      Total: {}", &self.statistic.total()));
         ctx.set_fancy(
-            PointF { x: 0.0, y: 5.0 },
+            PointF { x: 0.0, y: 8.0 },
             0,
             Radians::new(0.0),
             PointF { x: 2.0, y: 2.0 },
@@ -168,10 +328,46 @@ impl State {
             to_cp437('@'),
         )
     }
+
+    fn render_walls(&self, ctx: &mut BTerm) {
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                if self.walls[y][x] == Tile::Wall {
+                    ctx.set(x, y, GRAY, GRAY, to_cp437('#'));
+                }
+            }
+        }
+    }
+
+    // Draws the last SCREEN_WIDTH ticks of the infected curve as a bar chart
+    // in the dedicated HUD strip below the simulation grid (rows
+    // SCREEN_HEIGHT..WINDOW_HEIGHT), scaled to the series' own peak. Living
+    // there instead of on top of the grid means it never overdraws a wall or
+    // player cell, regardless of scenario.
+    fn render_sparkline(&self, ctx: &mut BTerm) {
+        let peak = self
+            .recorder
+            .series
+            .iter()
+            .map(|s| s.inflected)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let start = self.recorder.series.len().saturating_sub(SCREEN_WIDTH);
+
+        for (x, sample) in self.recorder.series[start..].iter().enumerate() {
+            let bar_height =
+                ((sample.inflected as f64 / peak as f64) * SPARKLINE_HEIGHT as f64).round() as usize;
+            for h in 0..bar_height {
+                ctx.set(x, WINDOW_HEIGHT - 1 - h, RED, BLACK, to_cp437('|'));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, EnumIter, PartialEq, Eq)]
 enum HealthState {
+    Exposed,
     Inflected,
     Immune,
     Susceptible,
@@ -185,7 +381,33 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    // Direction to step from `from` to an orthogonally-adjacent `to`.
+    fn towards(from: Point, to: Point) -> Direction {
+        if to.1 < from.1 {
+            Direction::Up
+        } else if to.1 > from.1 {
+            Direction::Down
+        } else if to.0 < from.0 {
+            Direction::Left
+        } else {
+            Direction::Right
+        }
+    }
+}
+
+// A player's current movement objective. GoTo/Return carry the destination;
+// Idle means "no destination, fall back to the random walk".
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AIGoal {
+    GoTo(Point),
+    Return(Point),
+    Idle,
+}
+
+// No longer Copy: the cached A* path (below) owns a Vec, so every call site
+// that needs an independent value now clones explicitly.
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Player {
     x: usize,
     y: usize,
@@ -193,8 +415,37 @@ struct Player {
     is_lounging: bool,
     steps: u32,
     health_state: HealthState,
+    // Number of ticks spent in the current health_state; reset on every transition.
+    ticks_in_state: u32,
+    // Set once an Inflected player rolls a fatal outcome; the tick loop
+    // removes tombstoned players from the map and the players list.
+    is_dead: bool,
+    // Where this player spawned; a Return goal routes back here.
+    home: Point,
+    goal: AIGoal,
+    // Cached A* route toward `goal`, consumed one cell per step taken;
+    // recomputed whenever the goal changes. A blocked next cell is waited
+    // out rather than routed around (see step_towards_goal).
+    path: Vec<Point>,
+    // Consecutive ticks spent pursuing a goal while facing a wall that
+    // blocks it; reset whenever the player moves or stops facing a wall.
+    // Guards against a goal that A* reports reachable but that can never
+    // actually be entered (see keep_moving) from freezing the player forever.
+    stuck_ticks: u32,
+    // Ticks left to wander (AIGoal::Idle) before the next-destination pick
+    // in advance() is allowed to hand out a new goal. Without this, giving
+    // up on a stuck goal just sets Idle, which advance() treats as "ready
+    // for a new goal" on the very next tick -- so a player stuck against one
+    // blocked anchor would immediately be pointed at another, potentially
+    // just as blocked, churning A* every MAX_STUCK_TICKS ticks instead of
+    // actually taking a break.
+    idle_cooldown: u32,
 }
 const MIN_STEP: u32 = 20;
+// How long a player can pursue a goal while standing still facing a wall or
+// the map edge before giving up on it and falling back to the random walk.
+// Being held up by other players does not count.
+const MAX_STUCK_TICKS: u32 = 10;
 // I am a player backend, and responsible for behavior of players.
 impl Player {
     fn new(
@@ -211,81 +462,213 @@ impl Player {
             is_lounging,
             health_state,
             steps: 0,
+            ticks_in_state: 0,
+            is_dead: false,
+            home: (x, y),
+            goal: AIGoal::Idle,
+            path: vec![],
+            stuck_ticks: 0,
+            idle_cooldown: 0,
         }
     }
 
+    // Decides this tick's direction/position, then writes the result into
+    // `back`. Collision checks consult both `front` (covers players not yet
+    // processed this tick, who still occupy their pre-move cell there) and
+    // `back` (covers players already moved this tick) — `front` alone is too
+    // stale to stop two players racing for the same cell, and `back` alone
+    // doesn't yet know where unprocessed players currently stand.
     fn keep_moving(
         &mut self,
-        map: &mut [[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
-    ) -> Option<(usize, usize)> {
-        self.change_dir(map);
-
-        if !self.end_way(map) {
-            /*
-            We should not remove player from old position because it has been left.
-            If we do that, successive player will recursively step in a empty position.
-             */
-            let ret = Some((self.x, self.y));
+        front: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        back: &mut [[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        walls: &WallGrid,
+        rng: &mut StdRng,
+    ) {
+        let pos_before = (self.x, self.y);
+        let pursuing_goal = !matches!(self.goal, AIGoal::Idle);
+
+        self.change_dir(front, back, walls, rng);
+
+        if !self.end_way(front, back, walls) {
             self.move_1_step();
+        }
 
-            /*
-            But we should put player in new position to prevent other player step into same position.
-            */
-            let _ = map[self.y][self.x].insert(self.clone()); //
-            return ret;
+        // A goal can be "found" by A* (the goal cell itself is exempt from
+        // the blocked check in astar::find_path) yet never actually
+        // enterable, e.g. an anchor that landed on a wall: step_towards_goal
+        // points at it every tick, and end_way correctly refuses to step
+        // onto it, freezing the player one cell short forever. Only count
+        // being blocked by a *wall* as stuck -- being blocked by another
+        // player in a crowd is expected and resolves on its own, and
+        // treating that as stuck too would abandon (and expensively
+        // re-path) perfectly good routes just because a neighbor hasn't
+        // stepped aside yet.
+        if pursuing_goal {
+            if (self.x, self.y) != pos_before || !self.facing_a_wall(walls) {
+                self.stuck_ticks = 0;
+            } else {
+                self.stuck_ticks += 1;
+                if self.stuck_ticks > MAX_STUCK_TICKS {
+                    self.goal = AIGoal::Idle;
+                    self.path.clear();
+                    self.stuck_ticks = 0;
+                    self.idle_cooldown = MAX_STUCK_TICKS;
+                }
+            }
         }
 
-        None
+        let _ = back[self.y][self.x].insert(self.clone());
     }
 
     fn change_dir(
         &mut self,
-        map: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        front: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        back: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        walls: &WallGrid,
+        rng: &mut StdRng,
     ) {
+        if self.step_towards_goal(front, back, walls) {
+            return;
+        }
+
         let old_dir = self.dir;
         if self.is_lounging && self.steps > MIN_STEP {
-            self.dir = Direction::iter().choose(&mut thread_rng()).unwrap();
+            self.dir = Direction::iter().choose(rng).unwrap();
             if self.dir != old_dir {
                 self.steps = 0;
             }
-        } else if self.end_way(map) {
+        } else if self.end_way(front, back, walls) {
             self.dir = Direction::iter()
                 .filter(|x| x != &self.dir)
-                .choose(&mut thread_rng())
+                .choose(rng)
                 .unwrap();
         }
     }
 
+    // Route toward the current AIGoal via A*, setting self.dir to the first
+    // step. Returns false (leaving self.dir untouched) when idle, already at
+    // the goal, or no path exists, so callers can fall back to the random walk.
+    fn step_towards_goal(
+        &mut self,
+        front: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        back: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        walls: &WallGrid,
+    ) -> bool {
+        let destination = match self.goal {
+            AIGoal::GoTo(p) | AIGoal::Return(p) => p,
+            AIGoal::Idle => return false,
+        };
+
+        if (self.x, self.y) == destination {
+            self.path.clear();
+            return false;
+        }
+
+        // Drop any waypoints we've already reached (including while waiting
+        // out a blocked cell below, which leaves the head in place to retry).
+        while self.path.first() == Some(&(self.x, self.y)) {
+            self.path.remove(0);
+        }
+
+        // Recompute only when we don't already have a route to this destination.
+        if self.path.last() != Some(&destination) {
+            self.path = find_path(
+                (self.x, self.y),
+                destination,
+                SCREEN_WIDTH,
+                SCREEN_HEIGHT,
+                |p| {
+                    front[p.1][p.0].is_some()
+                        || back[p.1][p.0].is_some()
+                        || walls[p.1][p.0] == Tile::Wall
+                },
+            )
+            .unwrap_or_default();
+        }
+
+        match self.path.first().copied() {
+            // Point at the next waypoint even if it's occupied right now;
+            // end_way/keep_moving simply won't step until it clears, and the
+            // head stays queued so we retry without recomputing the route.
+            Some(next) => {
+                self.dir = Direction::towards((self.x, self.y), next);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn end_way(
         &self,
-        map: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        front: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        back: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        walls: &WallGrid,
     ) -> bool {
+        let occupied = |y: usize, x: usize| front[y][x].is_some() || back[y][x].is_some();
         match self.dir {
-            Direction::Up => self.y == 0 || map[self.y - 1][self.x].is_some(),
-            Direction::Down => self.y == SCREEN_HEIGHT - 1 || map[self.y + 1][self.x].is_some(),
-            Direction::Left => self.x == 0 || map[self.y][self.x - 1].is_some(),
-            Direction::Right => self.x == SCREEN_WIDTH - 1 || map[self.y][self.x + 1].is_some(),
+            Direction::Up => {
+                self.y == 0 || occupied(self.y - 1, self.x) || walls[self.y - 1][self.x] == Tile::Wall
+            }
+            Direction::Down => {
+                self.y == SCREEN_HEIGHT - 1
+                    || occupied(self.y + 1, self.x)
+                    || walls[self.y + 1][self.x] == Tile::Wall
+            }
+            Direction::Left => {
+                self.x == 0 || occupied(self.y, self.x - 1) || walls[self.y][self.x - 1] == Tile::Wall
+            }
+            Direction::Right => {
+                self.x == SCREEN_WIDTH - 1
+                    || occupied(self.y, self.x + 1)
+                    || walls[self.y][self.x + 1] == Tile::Wall
+            }
         }
     }
 
-    fn around_people(
-        &self,
-        map: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
-    ) -> Vec<Player> {
-        let mut ret = vec![];
-        for y in self.y.saturating_sub(SAFE_DISTANCE)..(self.y + SAFE_DISTANCE).min(SCREEN_HEIGHT) {
-            for x in
-                self.x.saturating_sub(SAFE_DISTANCE)..(self.x + SAFE_DISTANCE).min(SCREEN_WIDTH)
-            {
-                let distance = (((self.x as i64 - x as i64).pow(2)
-                    + (self.y as i64 - y as i64).pow(2)) as f64)
-                    .sqrt();
-                if distance <= SAFE_DISTANCE as f64 {
-                    map[y][x].map(|player| ret.push(player));
-                }
+    // Whether the cell this player is currently facing is permanently
+    // impassable (a wall, or the edge of the map) rather than merely
+    // occupied by another player. Used to tell a genuine dead end apart
+    // from ordinary crowding, which clears on its own.
+    fn facing_a_wall(&self, walls: &WallGrid) -> bool {
+        match self.dir {
+            Direction::Up => self.y == 0 || walls[self.y - 1][self.x] == Tile::Wall,
+            Direction::Down => {
+                self.y == SCREEN_HEIGHT - 1 || walls[self.y + 1][self.x] == Tile::Wall
+            }
+            Direction::Left => self.x == 0 || walls[self.y][self.x - 1] == Tile::Wall,
+            Direction::Right => {
+                self.x == SCREEN_WIDTH - 1 || walls[self.y][self.x + 1] == Tile::Wall
             }
         }
-        ret
+    }
+
+    // Every occupied cell within SAFE_DISTANCE that self can actually see
+    // (line of sight via shadowcasting, so walls cast genuine shelter),
+    // paired with its distance from self.
+    fn visible_players(
+        &self,
+        map: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+        walls: &WallGrid,
+    ) -> Vec<(Player, f64)> {
+        field_of_view(
+            (self.x, self.y),
+            SAFE_DISTANCE,
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            |p| walls[p.1][p.0] == Tile::Wall,
+        )
+        .into_iter()
+        .filter(|&(x, y)| (x, y) != (self.x, self.y))
+        .filter_map(|(x, y)| {
+            let distance = (((self.x as i64 - x as i64).pow(2)
+                + (self.y as i64 - y as i64).pow(2)) as f64)
+                .sqrt();
+            map[y][x]
+                .as_ref()
+                .map(|player| (player.clone(), distance))
+        })
+        .collect()
     }
 
     fn move_1_step(&mut self) {
@@ -313,20 +696,23 @@ impl Player {
         };
     }
 
-    fn update_position_in_map(&self, map: &mut [[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]) {
-        let _ = map[self.y][self.x].insert(self.clone());
-    }
-
-    fn meet_infected(
-        &mut self,
+    // Probability that this (Susceptible) player gets exposed this tick, given
+    // every Inflected player within sight: p = 1 - (1 - beta).powi(n), with
+    // closer contacts weighted more heavily (beta scaled by 1/distance).
+    fn infection_probability(
+        &self,
         map: &[[Option<Player>; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
-    ) -> bool {
-        let around_people = self.around_people(map);
-        around_people
+        walls: &WallGrid,
+    ) -> f64 {
+        let escape_probability = self
+            .visible_players(map, walls)
             .iter()
-            .filter(|player| player.health_state == HealthState::Inflected)
-            .count()
-            > 0
+            .filter(|(player, _)| player.health_state == HealthState::Inflected)
+            .fold(1.0_f64, |escape, (_, distance)| {
+                let contact_beta = (BETA / distance.max(1.0)).min(1.0);
+                escape * (1.0 - contact_beta)
+            });
+        1.0 - escape_probability
     }
 
     fn render(&mut self, ctx: &mut BTerm) {
@@ -335,6 +721,7 @@ impl Player {
         let mut fg = GREEN;
         match self.health_state {
             HealthState::Immune => fg = GREEN,
+            HealthState::Exposed => fg = ORANGE,
             HealthState::Inflected => fg = RED,
             HealthState::Susceptible => fg = YELLOW,
         }
@@ -368,78 +755,241 @@ const SCREEN_WIDTH: usize = 100;
 const FRAME_TIME: f32 = 80.0;
 const SAFE_DISTANCE: usize = 5;
 
+// Number of shared "home"/"market" anchor points players path toward, creating
+// crowding hotspots.
+const ANCHOR_COUNT: usize = 6;
+
+// Height, in rows, of the infected-curve sparkline HUD strip.
+const SPARKLINE_HEIGHT: usize = 10;
+// Total console height: the simulation grid plus a dedicated HUD strip below
+// it for the sparkline, so the chart never overdraws a wall or player cell.
+const WINDOW_HEIGHT: usize = SCREEN_HEIGHT + SPARKLINE_HEIGHT;
+// File the epidemic time series is flushed to when the user presses S.
+const EPIDEMIC_CSV_PATH: &str = "epidemic_series.csv";
+
+// Per-contact transmission rate used by Player::infection_probability.
+const BETA: f64 = 0.15;
+// Mean number of ticks spent Exposed/Inflected before the next transition;
+// each tick rolls a 1/TICKS hazard, giving a geometric waiting time.
+const INCUBATION_TICKS: f64 = 5.0;
+const INFECTIOUS_TICKS: f64 = 10.0;
+// Probability an Inflected player dies instead of recovering when their
+// infectious period ends.
+const MORTALITY: f64 = 0.02;
+
+// Command-line switches for seeding and headless Monte-Carlo runs.
+struct Args {
+    seed: u64,
+    headless: bool,
+    ticks: u32,
+    trials: u32,
+    // Path to a JSON5 scenario file describing wall layout; open floor
+    // everywhere when unset.
+    scenario: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            seed: 0,
+            headless: false,
+            ticks: 200,
+            trials: 1,
+            scenario: None,
+        }
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut raw = std::env::args().skip(1);
+    while let Some(flag) = raw.next() {
+        match flag.as_str() {
+            "--seed" => {
+                if let Some(v) = raw.next().and_then(|v| v.parse().ok()) {
+                    args.seed = v;
+                }
+            }
+            "--headless" => args.headless = true,
+            "--ticks" => {
+                if let Some(v) = raw.next().and_then(|v| v.parse().ok()) {
+                    args.ticks = v;
+                }
+            }
+            "--trials" => {
+                if let Some(v) = raw.next().and_then(|v| v.parse().ok()) {
+                    args.trials = v;
+                }
+            }
+            "--scenario" => {
+                args.scenario = raw.next();
+            }
+            _ => {}
+        }
+    }
+    args
+}
+
+fn load_scenario_walls(scenario: &Option<String>) -> Box<WallGrid> {
+    match scenario {
+        Some(path) => load_walls(path),
+        None => Box::new([[Tile::Floor; SCREEN_WIDTH]; SCREEN_HEIGHT]),
+    }
+}
+
 fn main() -> BError {
-    let mut random = RandomNumberGenerator::new();
+    let args = parse_args();
     // input
     let (infected, immune, susceptible) = (1, 300, 400);
     let peoples = 1000;
 
-    let init_fn = Box::new(move || generate(peoples, infected, immune, susceptible));
+    if args.headless {
+        run_headless(
+            args.seed,
+            args.ticks,
+            args.trials,
+            peoples,
+            infected,
+            immune,
+            susceptible,
+            &args.scenario,
+        );
+        return Ok(());
+    }
 
-    let (players, map, s) = init_fn();
+    let walls = load_scenario_walls(&args.scenario);
+    let init_fn = make_init_fn(peoples, infected, immune, susceptible, walls.clone());
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let (players, map, s, anchors) = init_fn(&mut rng);
 
     let context = BTermBuilder::new()
-        .with_dimensions(SCREEN_WIDTH, SCREEN_HEIGHT)
+        .with_dimensions(SCREEN_WIDTH, WINDOW_HEIGHT)
         .with_tile_dimensions(8, 8)
         .with_title("Virus")
         .with_font("terminal8x8.png", 8, 8)
-        .with_simple_console(SCREEN_WIDTH, SCREEN_HEIGHT, "terminal8x8.png")
+        .with_simple_console(SCREEN_WIDTH, WINDOW_HEIGHT, "terminal8x8.png")
         .build()?;
 
-    main_loop(context, State::new(players, map, init_fn, s))
+    main_loop(
+        context,
+        State::new(players, map, init_fn, s, rng, anchors, walls),
+    )
 }
 
-fn generate(
+// Run `trials` independent seeded simulations for `ticks` steps each and
+// print the final Statistic for every trial, with no BTermBuilder/main_loop
+// involved. Lets users study attack-rate distributions across many seeds
+// instead of watching a single animation.
+#[allow(clippy::too_many_arguments)]
+fn run_headless(
+    seed: u64,
+    ticks: u32,
+    trials: u32,
     peoples: u32,
     infected: u32,
     immune: u32,
     susceptible: u32,
-) -> (
-    Vec<Player>,
-    Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]>,
-    Statistic,
+    scenario: &Option<String>,
 ) {
-    let mut random = RandomNumberGenerator::new();
-    let is_lounging = if random.range(0, 2) == 1 { true } else { false };
+    let walls = load_scenario_walls(scenario);
+    for trial in 0..trials {
+        let trial_seed = seed.wrapping_add(trial as u64);
+        let init_fn = make_init_fn(peoples, infected, immune, susceptible, walls.clone());
+        let mut rng = StdRng::seed_from_u64(trial_seed);
+        let (players, map, statistic, anchors) = init_fn(&mut rng);
+        let mut state = State::new(players, map, init_fn, statistic, rng, anchors, walls.clone());
+
+        for _ in 0..ticks {
+            state.advance();
+        }
+
+        println!("trial {} (seed {}):\n{}", trial, trial_seed, state.statistic);
+
+        let csv_path = format!("epidemic_trial_{trial}_seed_{trial_seed}.csv");
+        state
+            .recorder
+            .write_csv(&csv_path)
+            .expect("failed to write epidemic series csv");
+    }
+}
+
+fn make_init_fn(
+    peoples: u32,
+    infected: u32,
+    immune: u32,
+    susceptible: u32,
+    walls: Box<WallGrid>,
+) -> InitFn {
+    Box::new(move |rng| generate(rng, peoples, infected, immune, susceptible, &walls))
+}
+
+fn generate(
+    rng: &mut StdRng,
+    peoples: u32,
+    infected: u32,
+    immune: u32,
+    susceptible: u32,
+    walls: &WallGrid,
+) -> InitResult {
+    let is_lounging = rng.gen_bool(0.5);
 
     let mut statistic = Statistic::default();
 
     // begin
     let mut players = vec![];
-    let mut map: Box<[[Option<Player>; SCREEN_WIDTH]; SCREEN_HEIGHT]> =
-        Box::new([[None; SCREEN_WIDTH]; SCREEN_HEIGHT]);
+    let mut map: Box<Grid> = empty_grid();
 
     let mut count = 0;
 
-    (0..peoples).for_each(|i| {
-        let x = random.range(0, SCREEN_WIDTH);
-        let y = random.range(0, SCREEN_HEIGHT);
-        let dir = Direction::iter().choose(&mut thread_rng()).unwrap();
-        let health_state = generate_health_state(infected, immune, susceptible);
+    (0..peoples).for_each(|_| {
+        let x = rng.gen_range(0..SCREEN_WIDTH);
+        let y = rng.gen_range(0..SCREEN_HEIGHT);
+        let dir = Direction::iter().choose(rng).unwrap();
+        let health_state = generate_health_state(rng, infected, immune, susceptible);
         let player = Player::new(x, y, dir, is_lounging, health_state);
-        if map[y][x].is_none() {
+        if map[y][x].is_none() && walls[y][x] != Tile::Wall {
+            let _ = map[y][x].insert(player.clone());
             players.push(player);
-            let _ = map[y][x].insert(player);
             count += 1;
             match health_state {
                 HealthState::Inflected => statistic.inflected += 1,
                 HealthState::Immune => statistic.immune += 1,
                 HealthState::Susceptible => statistic.susceptible += 1,
+                HealthState::Exposed => statistic.exposed += 1,
             }
         }
     });
 
-    (players, map, statistic)
+    // Reject wall cells the same way player spawns do above; an anchor
+    // stuck inside a wall is unreachable forever, since A* goal cells are
+    // exempt from the blocked check and so "find" a path to a cell no one
+    // can actually step onto (see AIGoal::GoTo / step_towards_goal).
+    let anchors = (0..ANCHOR_COUNT)
+        .map(|_| loop {
+            let x = rng.gen_range(0..SCREEN_WIDTH);
+            let y = rng.gen_range(0..SCREEN_HEIGHT);
+            if walls[y][x] != Tile::Wall {
+                break (x, y);
+            }
+        })
+        .collect();
+
+    (players, map, statistic, anchors)
 }
 
-fn generate_health_state(inflected: u32, immune: u32, susceptible: u32) -> HealthState {
+fn generate_health_state(
+    rng: &mut StdRng,
+    inflected: u32,
+    immune: u32,
+    susceptible: u32,
+) -> HealthState {
     let items = [
         (HealthState::Inflected, inflected),
         (HealthState::Immune, immune),
         (HealthState::Susceptible, susceptible),
     ];
     let dist2 = WeightedIndex::new(items.iter().map(|item| item.1)).unwrap();
-    items[dist2.sample(&mut thread_rng())].0
+    items[dist2.sample(rng)].0
 }
 
 fn generate_health_state_sequence(
@@ -461,3 +1011,62 @@ fn generate_health_state_sequence(
 
     v.into_iter().cycle()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // Regression test for the double-buffer collision bug: two players must
+    // never end up sharing a cell, whether because an unprocessed player's
+    // current position was missed or an already-moved player's new position
+    // was overwritten.
+    #[test]
+    fn no_two_players_share_a_cell_after_many_ticks() {
+        let walls: Box<WallGrid> = Box::new([[Tile::Floor; SCREEN_WIDTH]; SCREEN_HEIGHT]);
+        let mut rng = StdRng::seed_from_u64(1);
+        let (players, map, statistic, anchors) = generate(&mut rng, 300, 100, 100, 100, &walls);
+        let init_fn = make_init_fn(300, 100, 100, 100, walls.clone());
+        let mut state = State::new(players, map, init_fn, statistic, rng, anchors, walls);
+
+        for _ in 0..300 {
+            state.advance();
+
+            let mut seen = HashSet::new();
+            for player in &state.players {
+                assert!(
+                    seen.insert((player.x, player.y)),
+                    "duplicate position ({}, {})",
+                    player.x,
+                    player.y
+                );
+            }
+        }
+    }
+
+    // Regression test for the SEIR transition rolls in advance(): every
+    // player who starts Inflected must eventually resolve to a terminal
+    // state (Immune or dead), with the population total never drifting.
+    #[test]
+    fn seir_transitions_preserve_total_and_eventually_resolve() {
+        let walls: Box<WallGrid> = Box::new([[Tile::Floor; SCREEN_WIDTH]; SCREEN_HEIGHT]);
+        let mut rng = StdRng::seed_from_u64(1);
+        let (players, map, statistic, anchors) = generate(&mut rng, 100, 100, 0, 0, &walls);
+        let total = statistic.total();
+        let init_fn = make_init_fn(100, 100, 0, 0, walls.clone());
+        let mut state = State::new(players, map, init_fn, statistic, rng, anchors, walls);
+
+        for _ in 0..2000 {
+            state.advance();
+            assert_eq!(state.statistic.total(), total, "population total drifted");
+        }
+
+        assert_eq!(state.statistic.exposed, 0, "exposed players never resolved");
+        assert_eq!(state.statistic.inflected, 0, "inflected players never resolved");
+        assert_eq!(
+            state.statistic.recovered + state.statistic.dead,
+            total,
+            "every player should have recovered or died"
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+use std::io;
+
+// One row of the epidemic time series: compartment counts at a single tick.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sample {
+    pub tick: u32,
+    pub susceptible: u32,
+    pub exposed: u32,
+    pub inflected: u32,
+    pub recovered: u32,
+    pub dead: u32,
+}
+
+// Appends a Sample every simulation step so the outbreak can be inspected
+// over time instead of only at the current instant.
+#[derive(Default)]
+pub struct Recorder {
+    pub series: Vec<Sample>,
+}
+
+impl Recorder {
+    pub fn record(&mut self, sample: Sample) {
+        self.series.push(sample);
+    }
+
+    pub fn write_csv(&self, path: &str) -> io::Result<()> {
+        let mut out = String::from("tick,susceptible,exposed,inflected,recovered,dead\n");
+        for s in &self.series {
+            out.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                s.tick, s.susceptible, s.exposed, s.inflected, s.recovered, s.dead
+            ));
+        }
+        fs::write(path, out)
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::{Tile, WallGrid, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// A rectangular wall segment (a building footprint, a corridor divider) in
+// scenario-file coordinates.
+#[derive(Debug, Deserialize)]
+struct WallRect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ScenarioFile {
+    #[serde(default)]
+    walls: Vec<WallRect>,
+}
+
+// Builds a wall grid from a JSON5 scenario file describing rectangular wall
+// segments (buildings, corridors). Cells outside every rect stay open floor.
+pub fn load_walls(path: &str) -> Box<WallGrid> {
+    let raw = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read scenario file {path}: {e}"));
+    let scenario: ScenarioFile =
+        json5::from_str(&raw).unwrap_or_else(|e| panic!("invalid scenario file {path}: {e}"));
+
+    let mut walls: Box<WallGrid> = Box::new([[Tile::Floor; SCREEN_WIDTH]; SCREEN_HEIGHT]);
+    for rect in &scenario.walls {
+        for y in rect.y..(rect.y + rect.height).min(SCREEN_HEIGHT) {
+            for x in rect.x..(rect.x + rect.width).min(SCREEN_WIDTH) {
+                walls[y][x] = Tile::Wall;
+            }
+        }
+    }
+    walls
+}